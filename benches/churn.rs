@@ -0,0 +1,33 @@
+use chord_dht::sim::*;
+
+/// Run churn scenarios with different failure rates and replication
+fn main() {
+	let rates = [0.0, 0.5, 1.0, 2.0];
+	// (fault_tolerance, replication_factor)
+	let fault_tolerance_replication = [(0, 1), (2, 3), (4, 5)];
+
+	for &(fault_tolerance, replication_factor) in fault_tolerance_replication.iter() {
+		for &rate in rates.iter() {
+			let config = SimConfig {
+				join_rate: rate,
+				leave_rate: rate / 4.0,
+				crash_rate: rate / 4.0,
+				fault_tolerance,
+				replication_factor,
+				..SimConfig::default()
+			};
+			let start = std::time::Instant::now();
+			let report = simulate(config.clone());
+			println!(
+				"join_rate={:.2} leave_rate={:.2} crash_rate={:.2} fault_tolerance={} replication_factor={} {} elapsed={:?}",
+				config.join_rate,
+				config.leave_rate,
+				config.crash_rate,
+				config.fault_tolerance,
+				config.replication_factor,
+				report,
+				start.elapsed()
+			);
+		}
+	}
+}
@@ -1,10 +1,35 @@
-use crate::{rpc::NodeServiceClient, core::DhtResult};
+use crate::{rpc::NodeServiceClient, core::{DhtResult, Config}};
 use tarpc::tokio_serde::formats::Bincode;
+use tokio::net::TcpStream;
+use tokio_util::codec::{Framed, LengthDelimitedCodec};
+use socket2::{SockRef, TcpKeepalive};
 use log::info;
 
 pub async fn setup_client(addr: &str) -> DhtResult<NodeServiceClient> {
+	setup_client_with_keepalive(addr, Config::default().tcp_keepalive).await
+}
+
+/// Connect to a node with TCP keepalive enabled
+/// Keepalive probes are sent after the connection is idle for `keepalive` ms
+/// (0 disables keepalive)
+pub async fn setup_client_with_keepalive(addr: &str, keepalive: u64) -> DhtResult<NodeServiceClient> {
 	info!("connecting to {}", addr);
-	let transport = tarpc::serde_transport::tcp::connect(addr,Bincode::default).await?;
+	let stream = connect_with_keepalive(addr, keepalive).await?;
+	let transport = tarpc::serde_transport::new(
+		Framed::new(stream, LengthDelimitedCodec::new()),
+		Bincode::default()
+	);
 	info!("connected to {}", addr);
 	Ok(NodeServiceClient::new(tarpc::client::Config::default(), transport).spawn())
 }
+
+/// Open a TCP connection and set keepalive on the socket
+pub async fn connect_with_keepalive(addr: &str, keepalive: u64) -> DhtResult<TcpStream> {
+	let stream = TcpStream::connect(addr).await?;
+	if keepalive > 0 {
+		let params = TcpKeepalive::new()
+			.with_time(std::time::Duration::from_millis(keepalive));
+		SockRef::from(&stream).set_tcp_keepalive(&params)?;
+	}
+	Ok(stream)
+}
@@ -15,7 +15,13 @@ pub struct Config {
 	/// Retrying n times if the RPC fails
 	pub retry_limit: u64,
 	/// Interval to retry connecting to the same node (in ms)
-	pub retry_interval: u64
+	pub retry_interval: u64,
+	/// Idle time before sending TCP keepalive probes (in ms, 0 to disable)
+	pub tcp_keepalive: u64,
+	/// Close pooled connections unused for this long (in ms, 0 to disable)
+	pub idle_timeout: u64,
+	/// Interval to periodically prune idle connections (in ms, 0 to disable)
+	pub idle_check_interval: u64
 }
 
 impl Default for Config {
@@ -27,7 +33,10 @@ impl Default for Config {
 			stabilize_interval: 200,
 			fix_finger_interval: 200,
 			retry_limit: 2,
-			retry_interval: 50
+			retry_interval: 50,
+			tcp_keepalive: 10000,
+			idle_timeout: 60000,
+			idle_check_interval: 10000
 		}
 	}
 }
@@ -1,6 +1,11 @@
 use std::{
 	collections::{HashMap},
-	sync::{Arc, RwLock}
+	sync::{
+		Arc,
+		RwLock,
+		atomic::{AtomicU64, Ordering}
+	},
+	time::Instant
 };
 use rand::{Rng, SeedableRng};
 use tarpc::{
@@ -37,6 +42,14 @@ impl std::fmt::Display for Node {
 	}
 }
 
+// Pooled connection to a remote node
+struct Connection {
+	client: NodeServiceClient,
+	// ms since epoch of the NodeServer
+	// (atomic so that it can be updated under the read lock)
+	last_used: AtomicU64
+}
+
 #[derive(Clone)]
 pub struct NodeServer {
 	node: Node,
@@ -48,7 +61,9 @@ pub struct NodeServer {
 	// Maintain (fault_tolerance + 1) successors for recovery
 	successor_list: Arc<RwLock<Vec<Node>>>,
 	// connection to remote nodes
-	connection_map: Arc<RwLock<HashMap<Digest, NodeServiceClient>>>
+	connection_map: Arc<RwLock<HashMap<Digest, Connection>>>,
+	// Base time to calculate last_used of connections
+	epoch: Instant
 }
 
 impl NodeServer {
@@ -68,7 +83,8 @@ impl NodeServer {
 			predecessor: Arc::new(RwLock::new(Some(node.clone()))),
 			finger_table: Arc::new(RwLock::new(finger_table)),
 			successor_list: Arc::new(RwLock::new(successor_list)),
-			connection_map: Arc::new(RwLock::new(HashMap::new()))
+			connection_map: Arc::new(RwLock::new(HashMap::new())),
+			epoch: Instant::now()
 		}
 	}
 
@@ -188,12 +204,37 @@ impl NodeServer {
 			}
 		});
 
+		// Periodically close idle connections
+		let server = self.clone();
+		let mut idle_check_rx = rx.clone();
+		let idle_check_interval = self.config.idle_check_interval;
+		let idle_check_handle = tokio::spawn(async move {
+			if idle_check_interval > 0 {
+				let mut interval = tokio::time::interval(
+					tokio::time::Duration::from_millis(idle_check_interval)
+				);
+
+				tokio::select! {
+					_ = async {
+						loop {
+							interval.tick().await;
+							server.prune_idle_connections();
+						}
+					} => (),
+					_ = idle_check_rx.changed() => {
+						debug!("{}: idle_check task stopped gracefully", server.node);
+					}
+				};
+			}
+		});
+
 		info!("{}: listening at {}", self.node, self.node.addr);
 		// An aggregated handle for all tasks
 		let joined_handle = future::join_all(vec![
 			listener_handle,
 			stabilize_handle,
-			fix_finger_handle
+			fix_finger_handle,
+			idle_check_handle
 		]);
 
 		Ok(ServerManager {
@@ -205,7 +246,7 @@ impl NodeServer {
 	// Calculate start field of finger table (see Table 1)
 	// k in [0, m)
 	pub fn finger_table_start(&self, k: usize) -> u64 {
-		self.node.id.wrapping_add(1 << k)
+		finger_table_start(self.node.id, k)
 	}
 	
 	async fn get_connection(&mut self, node: &Node) -> DhtResult<NodeServiceClient> {
@@ -213,16 +254,23 @@ impl NodeServer {
 		{
 			let map = self.connection_map.read().unwrap();
 			if let Some(c) = map.get(&node.id) {
+				c.last_used.store(self.elapsed_ms(), Ordering::Relaxed);
 				// client can be cloned with lost cost
-				return Ok(c.clone());
+				return Ok(c.client.clone());
 			}
 		}
 		{
 			debug!("{}: connecting to {}", self.node, node);
-			let c = crate::client::setup_client(&node.addr).await?;
+			let c = crate::client::setup_client_with_keepalive(
+				&node.addr,
+				self.config.tcp_keepalive
+			).await?;
 			debug!("{}: connected to {}", self.node, node);
 			let mut map = self.connection_map.write().unwrap();
-			map.insert(node.id, c.clone());
+			map.insert(node.id, Connection {
+				client: c.clone(),
+				last_used: AtomicU64::new(self.elapsed_ms())
+			});
 			return Ok(c);
 		}
 	}
@@ -233,6 +281,36 @@ impl NodeServer {
 		map.remove(&node.id);
 	}
 
+	// Time since epoch (in ms)
+	fn elapsed_ms(&self) -> u64 {
+		self.epoch.elapsed().as_millis() as u64
+	}
+
+	/// Number of pooled connections
+	pub fn num_connections(&self) -> usize {
+		self.connection_map.read().unwrap().len()
+	}
+
+	/// Close connections that have been idle longer than idle_timeout
+	/// (never closes any if idle_timeout is 0)
+	/// Returns the number of connections removed
+	pub fn prune_idle_connections(&self) -> usize {
+		let timeout = self.config.idle_timeout;
+		if timeout == 0 {
+			return 0;
+		}
+		let now = self.elapsed_ms();
+		let mut map = self.connection_map.write().unwrap();
+		let before = map.len();
+		// Dropping the client closes the underlying socket
+		map.retain(|_, c| now.saturating_sub(c.last_used.load(Ordering::Relaxed)) < timeout);
+		let removed = before - map.len();
+		if removed > 0 {
+			debug!("{}: pruned {} idle connections", self.node, removed);
+		}
+		removed
+	}
+
 	// Figure 7: n.join
 	pub async fn join(&mut self, node: &Node) -> DhtResult<()> {
 		debug!("{}: joining {}", self.node, node);
@@ -350,18 +428,13 @@ impl NodeServer {
 	// Figure 4: n.closest_preceding_finger
 	async fn closest_preceding_finger(&mut self, id: Digest) -> Node {
 		let table = self.finger_table.read().unwrap();
-		for i in (0..NUM_BITS).rev() {
-			let f = if i > 0 {
-				table[i].clone()
-			} else {
-				// table[0] is maintained by successor_list[0]
-				self.get_successor()
-			};
-			if in_range(f.id, self.node.id, id) {
-				return f;
-			};
+		// table[0] is maintained by successor_list[0]
+		let finger = |i: usize| if i > 0 { table[i].id } else { self.get_successor().id };
+		match closest_preceding_finger(self.node.id, id, finger) {
+			Some(0) => self.get_successor(),
+			Some(i) => table[i].clone(),
+			None => self.node.clone()
 		}
-		self.node.clone()
 	}
 
 	// Figure 7: n.notify
@@ -17,3 +17,18 @@ pub fn in_range(id: Digest, start: Digest, end: Digest) -> bool {
 		id > start || id < end
 	}
 }
+
+// Calculate start field of finger table (see Table 1)
+// k in [0, m)
+pub fn finger_table_start(id: Digest, k: usize) -> Digest {
+	id.wrapping_add(1 << k)
+}
+
+// Figure 4: n.closest_preceding_finger
+// finger(i) returns the id of the i-th finger of node id
+// Returns the index of the closest finger preceding key
+pub fn closest_preceding_finger<F: Fn(usize) -> Digest>(id: Digest, key: Digest, finger: F) -> Option<usize> {
+	(0..NUM_BITS)
+		.rev()
+		.find(|&i| in_range(finger(i), id, key))
+}
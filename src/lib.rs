@@ -2,3 +2,5 @@ pub mod core;
 pub mod client;
 pub mod server;
 pub mod rpc;
+#[cfg(feature = "sim")]
+pub mod sim;
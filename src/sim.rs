@@ -0,0 +1,543 @@
+//! Churn simulation over virtual nodes
+//!
+//! This is a protocol model, not the crate's implementation:
+//! virtual nodes keep the same routing state as `NodeServer` and follow
+//! the same steps as node.rs for join, stabilize, notify, fix_finger,
+//! find_predecessor, get and set (replicate), but run in-process without RPC.
+//! There is no graceful leave and no key transfer on join or failure:
+//! a node leaves by stopping (`ServerManager::stop`), which other nodes
+//! can't tell apart from a crash.
+//! An RPC to a stopped or crashed node is modelled as a failed call.
+//! A node left without any live successor keeps serving RPCs with its
+//! stale state but no longer stabilizes (the stabilize task panics).
+//!
+//! Differences from node.rs, needed for the ring to repair itself:
+//! * A node clears its predecessor once the predecessor has failed
+//!   (failure detection through a failed RPC)
+//! * stabilize still adopts the successor and notifies it when the
+//!   successor has no predecessor (as in Figure 7) instead of returning
+//!
+//! Time advances in rounds: each round applies Poisson-distributed
+//! joins, leaves and crashes, runs one stabilize and fix_finger pass
+//! on every live node and issues random lookups.
+
+use std::collections::{BTreeMap, HashSet};
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use crate::core::ring::*;
+
+#[derive(Clone, Debug)]
+pub struct SimConfig {
+	/// Number of nodes in the ring before churn starts
+	pub initial_nodes: usize,
+	/// Expected number of joins per round
+	pub join_rate: f64,
+	/// Expected number of leaves (nodes stopped) per round
+	pub leave_rate: f64,
+	/// Expected number of crashes per round
+	pub crash_rate: f64,
+	/// Number of rounds with churn
+	pub churn_rounds: u64,
+	/// Give up waiting for convergence after n rounds without churn
+	pub max_convergence_rounds: u64,
+	/// Number of random lookups issued per round
+	pub lookups_per_round: u64,
+	/// Number of keys set before churn starts
+	pub num_keys: usize,
+	/// Tolerate at most n node failures
+	pub fault_tolerance: u64,
+	/// Replicate data in k successors (1 <= k <= n+1)
+	pub replication_factor: u64,
+	/// Number of random fingers each node fixes per round
+	pub fix_fingers_per_round: usize,
+	/// Seed of the random generator (node ids and keys are drawn from it)
+	pub seed: u64
+}
+
+impl Default for SimConfig {
+	fn default() -> Self {
+		Self {
+			initial_nodes: 100,
+			join_rate: 1.0,
+			leave_rate: 0.25,
+			crash_rate: 0.25,
+			churn_rounds: 100,
+			max_convergence_rounds: 1000,
+			lookups_per_round: 100,
+			num_keys: 1000,
+			fault_tolerance: 2,
+			replication_factor: 3,
+			fix_fingers_per_round: 1,
+			seed: 0
+		}
+	}
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct SimReport {
+	pub joins: u64,
+	pub leaves: u64,
+	pub crashes: u64,
+	/// Nodes left without any live successor
+	/// (they stay in the ring but stop stabilizing)
+	pub stuck: u64,
+	/// Live nodes at the end of the simulation
+	pub final_nodes: usize,
+	pub lookups: u64,
+	/// Lookups that returned the node actually responsible for the key
+	pub successful_lookups: u64,
+	pub keys: usize,
+	/// Keys with no live replica left on any node
+	pub lost_keys: usize,
+	/// Keys that a get from a random node fails to read at the end
+	/// (includes keys whose replicas are alive but no longer
+	/// on the successors responsible for them)
+	pub unreadable_keys: usize,
+	/// Rounds needed to repair all successors and predecessors after churn stops
+	/// (None if the ring didn't converge within max_convergence_rounds,
+	/// which is always the case once a node is stuck)
+	pub convergence_rounds: Option<u64>
+}
+
+impl SimReport {
+	pub fn lookup_success_rate(&self) -> f64 {
+		if self.lookups == 0 {
+			return 1.0;
+		}
+		self.successful_lookups as f64 / self.lookups as f64
+	}
+
+	pub fn data_loss_rate(&self) -> f64 {
+		if self.keys == 0 {
+			return 0.0;
+		}
+		self.lost_keys as f64 / self.keys as f64
+	}
+
+	pub fn unreadable_rate(&self) -> f64 {
+		if self.keys == 0 {
+			return 0.0;
+		}
+		self.unreadable_keys as f64 / self.keys as f64
+	}
+}
+
+impl std::fmt::Display for SimReport {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		let convergence = match self.convergence_rounds {
+			Some(r) => r.to_string(),
+			None => "-".to_string()
+		};
+		write!(
+			f,
+			"joins={} leaves={} crashes={} stuck={} nodes={} lookup_success={:.4} data_loss={:.4} unreadable={:.4} convergence_rounds={}",
+			self.joins,
+			self.leaves,
+			self.crashes,
+			self.stuck,
+			self.final_nodes,
+			self.lookup_success_rate(),
+			self.data_loss_rate(),
+			self.unreadable_rate(),
+			convergence
+		)
+	}
+}
+
+// Routing state of a virtual node (same fields as NodeServer)
+struct VirtualNode {
+	predecessor: Option<Digest>,
+	// The first entry is maintained by successor_list[0]
+	finger_table: Vec<Digest>,
+	successor_list: Vec<Digest>,
+	// Digests of the keys stored locally
+	store: HashSet<Digest>,
+	// No live successor left (stabilize no longer runs)
+	stuck: bool
+}
+
+pub struct Simulator {
+	config: SimConfig,
+	rng: StdRng,
+	// Live nodes only (stopped and crashed nodes are removed)
+	nodes: BTreeMap<Digest, VirtualNode>,
+	keys: Vec<Digest>,
+	report: SimReport
+}
+
+impl Simulator {
+	pub fn new(config: SimConfig) -> Self {
+		assert!(config.initial_nodes != 0, "initial_nodes equal to 0");
+		assert!(config.replication_factor != 0, "replication_factor equal to 0");
+		assert!(config.replication_factor <= config.fault_tolerance + 1, "replication_factor greater than fault_tolerance + 1");
+
+		let rng = StdRng::seed_from_u64(config.seed);
+		let mut sim = Simulator {
+			config,
+			rng,
+			nodes: BTreeMap::new(),
+			keys: Vec::new(),
+			report: SimReport::default()
+		};
+		sim.init_ring();
+		sim
+	}
+
+	/// Run the churn rounds followed by the convergence rounds
+	pub fn run(mut self) -> SimReport {
+		for _ in 0..self.config.churn_rounds {
+			self.churn();
+			self.maintain();
+			self.lookups();
+		}
+
+		for r in 0..self.config.max_convergence_rounds {
+			if self.converged() {
+				self.report.convergence_rounds = Some(r);
+				break;
+			}
+			self.maintain();
+		}
+		if self.report.convergence_rounds.is_none() && self.converged() {
+			self.report.convergence_rounds = Some(self.config.max_convergence_rounds);
+		}
+
+		self.report.final_nodes = self.nodes.len();
+		self.report.keys = self.keys.len();
+		self.report.lost_keys = self.keys.iter()
+			.filter(|k| !self.nodes.values().any(|n| n.store.contains(k)))
+			.count();
+		let keys = self.keys.clone();
+		for key in keys {
+			let from = self.random_node();
+			if !self.get(from, key) {
+				self.report.unreadable_keys += 1;
+			}
+		}
+		self.report
+	}
+
+	// Build a consistent ring and set the initial keys on it
+	fn init_ring(&mut self) {
+		for _ in 0..self.config.initial_nodes {
+			let id = self.new_id();
+			self.nodes.insert(id, VirtualNode {
+				predecessor: None,
+				finger_table: Vec::new(),
+				successor_list: Vec::new(),
+				store: HashSet::new(),
+				stuck: false
+			});
+		}
+
+		let ids: Vec<Digest> = self.nodes.keys().cloned().collect();
+		for id in ids {
+			let predecessor = self.true_predecessor(id);
+			let successor_list = self.true_successor_list(id);
+			let finger_table = (0..NUM_BITS)
+				.map(|k| self.owner(finger_table_start(id, k)))
+				.collect();
+			let n = self.nodes.get_mut(&id).unwrap();
+			n.predecessor = Some(predecessor);
+			n.finger_table = finger_table;
+			n.successor_list = successor_list;
+		}
+
+		for _ in 0..self.config.num_keys {
+			let key = self.rng.gen::<Digest>();
+			let from = self.random_node();
+			self.set(from, key);
+			self.keys.push(key);
+		}
+	}
+
+	fn new_id(&mut self) -> Digest {
+		loop {
+			let id = self.rng.gen::<Digest>();
+			if !self.nodes.contains_key(&id) {
+				return id;
+			}
+		}
+	}
+
+	fn alive(&self, id: Digest) -> bool {
+		self.nodes.contains_key(&id)
+	}
+
+	// Node actually responsible for id among live nodes
+	fn owner(&self, id: Digest) -> Digest {
+		match self.nodes.range(id..).next() {
+			Some((k, _)) => *k,
+			None => *self.nodes.keys().next().unwrap()
+		}
+	}
+
+	fn true_successor(&self, id: Digest) -> Digest {
+		self.owner(id.wrapping_add(1))
+	}
+
+	fn true_predecessor(&self, id: Digest) -> Digest {
+		match self.nodes.range(..id).next_back() {
+			Some((k, _)) => *k,
+			None => *self.nodes.keys().next_back().unwrap()
+		}
+	}
+
+	fn true_successor_list(&self, id: Digest) -> Vec<Digest> {
+		let mut list = Vec::new();
+		let mut n = id;
+		for _ in 0..(self.config.fault_tolerance + 1) {
+			n = self.true_successor(n);
+			list.push(n);
+		}
+		list
+	}
+
+	// Number of events in a round following Poisson(rate) (Knuth's algorithm)
+	fn poisson(&mut self, rate: f64) -> u64 {
+		if rate <= 0.0 {
+			return 0;
+		}
+		let limit = (-rate).exp();
+		let mut count = 0;
+		let mut p = self.rng.gen::<f64>();
+		while p > limit {
+			count += 1;
+			p *= self.rng.gen::<f64>();
+		}
+		count
+	}
+
+	fn random_node(&mut self) -> Digest {
+		let index = self.rng.gen_range(0..self.nodes.len());
+		*self.nodes.keys().nth(index).unwrap()
+	}
+
+	fn churn(&mut self) {
+		let joins = self.poisson(self.config.join_rate);
+		let leaves = self.poisson(self.config.leave_rate);
+		let crashes = self.poisson(self.config.crash_rate);
+
+		for _ in 0..joins {
+			let bootstrap = self.random_node();
+			let id = self.new_id();
+			self.join(id, bootstrap);
+		}
+		// Stopping a node looks the same as a crash to other nodes
+		// Always keep at least one node alive
+		for _ in 0..leaves {
+			if self.nodes.len() > 1 {
+				let id = self.random_node();
+				self.nodes.remove(&id);
+				self.report.leaves += 1;
+			}
+		}
+		for _ in 0..crashes {
+			if self.nodes.len() > 1 {
+				let id = self.random_node();
+				self.nodes.remove(&id);
+				self.report.crashes += 1;
+			}
+		}
+	}
+
+	// One round of periodic tasks on every live node
+	fn maintain(&mut self) {
+		let ids: Vec<Digest> = self.nodes.keys().cloned().collect();
+		for &id in ids.iter() {
+			self.check_predecessor(id);
+		}
+		for id in ids {
+			if !self.nodes[&id].stuck {
+				self.stabilize(id);
+			}
+			for _ in 0..self.config.fix_fingers_per_round {
+				let index = self.rng.gen_range(1..NUM_BITS);
+				self.fix_finger(id, index);
+			}
+		}
+	}
+
+	fn lookups(&mut self) {
+		for _ in 0..self.config.lookups_per_round {
+			let from = self.random_node();
+			let key = self.rng.gen::<Digest>();
+			self.report.lookups += 1;
+			if let Some(succ_list) = self.find_successor_list(from, key) {
+				if succ_list[0] == self.owner(key) {
+					self.report.successful_lookups += 1;
+				}
+			}
+		}
+	}
+
+	// All successors and predecessors are correct
+	fn converged(&self) -> bool {
+		self.nodes.iter().all(|(id, n)| {
+			n.successor_list[0] == self.true_successor(*id)
+				&& n.predecessor == Some(self.true_predecessor(*id))
+		})
+	}
+
+	// NodeServer::new followed by Figure 7: n.join
+	// (the node doesn't start if join fails)
+	fn join(&mut self, id: Digest, bootstrap: Digest) {
+		let successor_list = match self.find_successor_list(bootstrap, id) {
+			Some(v) => v,
+			None => return
+		};
+		self.nodes.insert(id, VirtualNode {
+			predecessor: None,
+			finger_table: vec![id; NUM_BITS],
+			successor_list,
+			store: HashSet::new(),
+			stuck: false
+		});
+		self.report.joins += 1;
+	}
+
+	// Clear the predecessor once it has failed
+	// (not in node.rs, see module doc)
+	fn check_predecessor(&mut self, id: Digest) {
+		if let Some(p) = self.nodes[&id].predecessor {
+			if !self.alive(p) {
+				self.nodes.get_mut(&id).unwrap().predecessor = None;
+			}
+		}
+	}
+
+	// Figure 7: n.stabilize
+	fn stabilize(&mut self, id: Digest) {
+		let successor_list = self.nodes[&id].successor_list.clone();
+		for mut succ in successor_list.into_iter() {
+			if !self.alive(succ) {
+				// Try next successor
+				continue;
+			}
+			// node.rs returns here if succ has no predecessor (see module doc)
+			if let Some(x) = self.nodes[&succ].predecessor {
+				if in_range(x, id, succ) {
+					if !self.alive(x) {
+						// Try next successor
+						continue;
+					}
+					succ = x;
+				}
+			}
+
+			let mut new_succ_list = self.nodes[&succ].successor_list.clone();
+			new_succ_list.pop();
+			new_succ_list.insert(0, succ);
+			self.nodes.get_mut(&id).unwrap().successor_list = new_succ_list;
+			self.notify(succ, id);
+			return;
+		}
+		// no live successors (NodeServer panics in the stabilize task)
+		self.nodes.get_mut(&id).unwrap().stuck = true;
+		self.report.stuck += 1;
+	}
+
+	// Figure 7: n.notify
+	fn notify(&mut self, id: Digest, node: Digest) {
+		let n = self.nodes.get_mut(&id).unwrap();
+		if let Some(p) = n.predecessor {
+			if !in_range(node, p, id) {
+				return;
+			}
+		}
+		n.predecessor = Some(node);
+	}
+
+	// Figure 7: n.fix_fingers
+	fn fix_finger(&mut self, id: Digest, index: usize) {
+		if let Some(succ) = self.find_successor_list(id, finger_table_start(id, index)) {
+			self.nodes.get_mut(&id).unwrap().finger_table[index] = succ[0];
+		}
+	}
+
+	// A modified version using successor_list
+	// from figure 4: n.find_successor
+	fn find_successor_list(&self, from: Digest, id: Digest) -> Option<Vec<Digest>> {
+		let n = self.find_predecessor(from, id)?;
+		Some(self.nodes[&n].successor_list.clone())
+	}
+
+	// Figure 4: n.find_predecessor
+	// Returns None if a hop fails or the lookup doesn't terminate
+	fn find_predecessor(&self, from: Digest, id: Digest) -> Option<Digest> {
+		let mut n = from;
+		let mut succ = self.nodes[&n].successor_list[0];
+		let mut hops = 0;
+
+		// stop when id in (n, succ]
+		while !(in_range(id, n, succ) || id == succ) {
+			hops += 1;
+			if hops > self.nodes.len() {
+				return None;
+			}
+			n = self.closest_preceding_finger(n, id);
+			if !self.alive(n) {
+				return None;
+			}
+			succ = self.nodes[&n].successor_list[0];
+		}
+		Some(n)
+	}
+
+	// Figure 4: n.closest_preceding_finger
+	fn closest_preceding_finger(&self, id: Digest, key: Digest) -> Digest {
+		let node = &self.nodes[&id];
+		// table[0] is maintained by successor_list[0]
+		let finger = |i: usize| if i > 0 { node.finger_table[i] } else { node.successor_list[0] };
+		match closest_preceding_finger(id, key, finger) {
+			Some(i) => finger(i),
+			None => id
+		}
+	}
+
+	// Get key on the ring (same steps as NodeServer::get)
+	fn get(&self, from: Digest, key: Digest) -> bool {
+		// Try reading from local replica first
+		if self.nodes[&from].store.contains(&key) {
+			return true;
+		}
+
+		let succ_list = match self.find_successor_list(from, key) {
+			Some(v) => v,
+			None => return false
+		};
+		for succ in succ_list {
+			if self.alive(succ) {
+				return self.nodes[&succ].store.contains(&key);
+			}
+			// Continue trying next replica
+		}
+		false
+	}
+
+	// Set key on the ring (same steps as NodeServer::set and replicate)
+	fn set(&mut self, from: Digest, key: Digest) {
+		let succ_list = match self.find_successor_list(from, key) {
+			Some(v) => v,
+			None => return
+		};
+		let owner = succ_list[0];
+		if !self.alive(owner) {
+			return;
+		}
+
+		// replicate it locally and to (replication_factor - 1) successors
+		let num = (self.config.replication_factor - 1) as usize;
+		let mut replicas = vec![owner];
+		replicas.extend(self.nodes[&owner].successor_list.iter().take(num));
+		for r in replicas {
+			if let Some(n) = self.nodes.get_mut(&r) {
+				n.store.insert(key);
+			}
+		}
+	}
+}
+
+/// Run a single churn scenario
+pub fn simulate(config: SimConfig) -> SimReport {
+	Simulator::new(config).run()
+}
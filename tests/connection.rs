@@ -0,0 +1,81 @@
+use chord_dht::{
+	core::{
+		config::*,
+		Node,
+		NodeServer
+	},
+	client::connect_with_keepalive
+};
+use socket2::SockRef;
+
+/// Test pruning idle connections in the pool
+#[tokio::test]
+async fn test_idle_connections() -> anyhow::Result<()> {
+	env_logger::init();
+	// Node 0
+	let n0 = Node {
+		addr: "localhost:9800".to_string(),
+		id: 0
+	};
+	// Node 1
+	let n1 = Node {
+		addr: "localhost:9801".to_string(),
+		id: u64::MAX / 2
+	};
+
+	// Disable auto fix_finger, stabilize and idle check
+	let config = Config {
+		fix_finger_interval: 0,
+		stabilize_interval: 0,
+		idle_check_interval: 0,
+		idle_timeout: 100,
+		..Config::default()
+	};
+	let mut s0 = NodeServer::new(n0.clone(), config.clone());
+	let m0 = s0.start(None).await?;
+	s0.stabilize().await;
+
+	// Node 1 joins node 0
+	let mut s1 = NodeServer::new(n1.clone(), config.clone());
+	let m1 = s1.start(Some(n0.clone())).await?;
+	s1.stabilize().await;
+	s0.stabilize().await;
+	assert!(s0.num_connections() > 0);
+	assert!(s1.num_connections() > 0);
+
+	// Recently used connections are kept
+	assert_eq!(s0.prune_idle_connections(), 0);
+
+	tokio::time::sleep(tokio::time::Duration::from_millis(200)).await;
+	assert!(s0.prune_idle_connections() > 0);
+	assert!(s1.prune_idle_connections() > 0);
+	assert_eq!(s0.num_connections(), 0);
+	assert_eq!(s1.num_connections(), 0);
+
+	// Connections are re-established on demand
+	s1.stabilize().await;
+	s0.stabilize().await;
+	assert_eq!(s0.get_successor().id, n1.id);
+	assert_eq!(s1.get_successor().id, n0.id);
+
+	m0.stop().await?;
+	m1.stop().await?;
+	Ok(())
+}
+
+/// Test keepalive is set on client sockets
+#[tokio::test]
+async fn test_keepalive() -> anyhow::Result<()> {
+	let listener = tokio::net::TcpListener::bind("localhost:9810").await?;
+	let addr = listener.local_addr()?.to_string();
+
+	let stream = connect_with_keepalive(&addr, 5000).await?;
+	let sock = SockRef::from(&stream);
+	assert!(sock.keepalive()?);
+	assert_eq!(sock.keepalive_time()?, std::time::Duration::from_secs(5));
+
+	// 0 disables keepalive
+	let stream = connect_with_keepalive(&addr, 0).await?;
+	assert!(!SockRef::from(&stream).keepalive()?);
+	Ok(())
+}
@@ -0,0 +1,78 @@
+#![cfg(feature = "sim")]
+use chord_dht::sim::*;
+
+/// Test churn simulation
+#[test]
+fn test_sim() {
+	// Without churn every lookup succeeds and all keys are readable
+	let report = simulate(SimConfig {
+		join_rate: 0.0,
+		leave_rate: 0.0,
+		crash_rate: 0.0,
+		..SimConfig::default()
+	});
+	assert_eq!(report.lookup_success_rate(), 1.0);
+	assert_eq!(report.lost_keys, 0);
+	assert_eq!(report.unreadable_keys, 0);
+	assert_eq!(report.convergence_rounds, Some(0));
+
+	// Joins alone never drop replicas and the ring converges
+	let report = simulate(SimConfig {
+		join_rate: 1.0,
+		leave_rate: 0.0,
+		crash_rate: 0.0,
+		..SimConfig::default()
+	});
+	assert!(report.joins > 0);
+	assert_eq!(report.lost_keys, 0);
+	assert!(report.convergence_rounds.is_some());
+
+	// Same seed gives the same result
+	let config = SimConfig::default();
+	let r1 = simulate(config.clone());
+	let r2 = simulate(config);
+	assert_eq!(r1.to_string(), r2.to_string());
+}
+
+/// Test churn simulation with failures
+#[test]
+fn test_sim_failures() {
+	// Fewer failures than fault_tolerance: no node gets stuck
+	// and the ring converges
+	let config = SimConfig {
+		join_rate: 0.5,
+		leave_rate: 0.0,
+		crash_rate: 0.02,
+		fault_tolerance: 4,
+		replication_factor: 5,
+		..SimConfig::default()
+	};
+	let report = simulate(config.clone());
+	assert!(report.crashes > 0);
+	assert!(report.leaves + report.crashes < config.fault_tolerance);
+	assert!(report.final_nodes > 0);
+	assert_eq!(report.stuck, 0);
+	assert!(report.convergence_rounds.is_some());
+
+	// More replicas lose less data under heavy churn
+	let config = SimConfig {
+		join_rate: 1.0,
+		leave_rate: 0.5,
+		crash_rate: 0.5,
+		fault_tolerance: 4,
+		..SimConfig::default()
+	};
+	let r1 = simulate(SimConfig {
+		replication_factor: 1,
+		..config.clone()
+	});
+	let r5 = simulate(SimConfig {
+		replication_factor: 5,
+		..config
+	});
+	assert!(r1.leaves > 0 && r1.crashes > 0);
+	assert!(r1.final_nodes > 0);
+	assert!(r5.final_nodes > 0);
+	assert!(r1.data_loss_rate() > 0.0);
+	assert!(r5.data_loss_rate() < r1.data_loss_rate());
+}